@@ -0,0 +1,121 @@
+//! Embedded multi-file asset bundles.
+//!
+//! `Engine::initial_html` previously had to produce one monolithic HTML
+//! string, which is painful once an app ships CSS/JS/images and Markdown
+//! docs alongside it. [build_initial_html] lets an engine keep those as
+//! separate files in an `include_dir::Dir` instead: it inlines referenced
+//! stylesheets and scripts (mirroring the `inline_style`/`inline_script`
+//! approach of the web-view fork this is modeled on) and renders any
+//! referenced `.md` file to HTML via `comrak`, producing a single
+//! self-contained page suitable for `Content::Html`.
+use anyhow::{anyhow, Result};
+use comrak::ComrakOptions;
+use include_dir::Dir;
+
+/// Build a single self-contained HTML page from `entry` (a path inside
+/// `dir`): `<link rel="stylesheet" href="...">` and `<script src="...">`
+/// tags are replaced with their referenced file's contents inlined, and
+/// `<div data-markdown="...">` placeholders are replaced with the
+/// referenced `.md` file rendered to HTML via `comrak`, using
+/// `markdown_opts`.
+pub fn build_initial_html(dir: &Dir, entry: &str, markdown_opts: &ComrakOptions) -> Result<String> {
+    let html = read_asset(dir, entry)?;
+    let html = inline_tag(dir, &html, r#"<link rel="stylesheet" href=""#, r#"">"#, |source| {
+        Ok(format!("<style>{}</style>", source))
+    })?;
+    let html = inline_tag(dir, &html, r#"<script src=""#, r#""></script>"#, |source| {
+        Ok(format!("<script>{}</script>", source))
+    })?;
+    let html = inline_tag(dir, &html, r#"<div data-markdown=""#, r#""></div>"#, |source| {
+        Ok(comrak::markdown_to_html(source, markdown_opts))
+    })?;
+    Ok(html)
+}
+
+/// read a file out of `dir` as utf-8, or fail with the embedded path
+fn read_asset(dir: &Dir, path: &str) -> Result<String> {
+    dir.get_file(path)
+        .ok_or_else(|| anyhow!("asset bundle has no file {:?}", path))?
+        .contents_utf8()
+        .ok_or_else(|| anyhow!("asset {:?} is not valid utf-8", path))
+        .map(str::to_string)
+}
+
+/// Replace every `prefix<path>suffix` occurrence in `html` with
+/// `render(contents of <path> read from dir)`.
+fn inline_tag(
+    dir: &Dir,
+    html: &str,
+    prefix: &str,
+    suffix: &str,
+    render: impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(prefix) {
+        out.push_str(&rest[..start]);
+        let after_prefix = &rest[start + prefix.len()..];
+        let end = after_prefix
+            .find(suffix)
+            .ok_or_else(|| anyhow!("unterminated tag starting with {:?}", prefix))?;
+        let path = &after_prefix[..end];
+        out.push_str(&render(&read_asset(dir, path)?)?);
+        rest = &after_prefix[end + suffix.len()..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use include_dir::{include_dir, Dir};
+
+    static FIXTURE: Dir = include_dir!("$CARGO_MANIFEST_DIR/src/asset_test_fixture");
+
+    #[test]
+    fn inline_tag_inlines_every_match() {
+        let html = inline_tag(&FIXTURE, "<p>[a.txt][b.txt]</p>", "[", "]", |source| {
+            Ok(source.to_uppercase())
+        })
+        .unwrap();
+        assert_eq!(html, "<p>HELLO\nWORLD\n</p>");
+    }
+
+    #[test]
+    fn inline_tag_leaves_html_without_the_prefix_untouched() {
+        let html = inline_tag(&FIXTURE, "<p>nothing to inline here</p>", "[", "]", |source| {
+            Ok(source.to_uppercase())
+        })
+        .unwrap();
+        assert_eq!(html, "<p>nothing to inline here</p>");
+    }
+
+    #[test]
+    fn inline_tag_fails_on_an_unterminated_tag() {
+        let err = inline_tag(&FIXTURE, "<p>[a.txt</p>", "[", "]", |source| {
+            Ok(source.to_uppercase())
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("unterminated tag"));
+    }
+
+    #[test]
+    fn inline_tag_fails_when_the_referenced_asset_is_missing() {
+        let err = inline_tag(&FIXTURE, "<p>[missing.txt]</p>", "[", "]", |source: &str| {
+            Ok(source.to_string())
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("missing.txt"));
+    }
+
+    #[test]
+    fn build_initial_html_inlines_style_script_and_markdown() {
+        let html =
+            build_initial_html(&FIXTURE, "page.html", &ComrakOptions::default()).unwrap();
+        assert!(html.contains("<style>body { color: red; }\n</style>"));
+        assert!(html.contains("<script>console.log(1);\n</script>"));
+        assert!(html.contains("<h1>Hello</h1>"));
+        assert!(!html.contains("data-markdown"));
+    }
+}
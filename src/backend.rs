@@ -0,0 +1,36 @@
+//! Abstraction over the windowing/webview toolkit used to host an [EngineTrait]
+//! implementation, so that [crate::UserData::run_engine_with_webview] does not
+//! depend directly on any single webview crate.
+//!
+//! Two drivers are provided behind cargo features: `backend-web-view` (the
+//! default, built on the `web-view` crate) and `backend-wry` (built on `wry`,
+//! which gets us WebView2 on Windows and a maintained WebKitGTK binding on
+//! Linux instead of `web-view`'s MSHTML/old-WebKitGTK backends). Exactly one
+//! should be enabled; see `backend_web_view` and `backend_wry` for the
+//! implementations.
+use crate::{UserData, WebParams};
+use anyhow::Result;
+use narthex_engine_trait::{ActionTrait, EngineTrait, ResponseTrait};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The three operations `run_engine_with_webview` needs from a webview
+/// toolkit: build a window from [WebParams] plus the initial HTML, register
+/// an invoke handler that deserializes an `Engine::Action` and feeds back a
+/// serialized `Engine::Response`, and run the event loop to completion.
+///
+/// A backend owns all three because the toolkits this crate supports build
+/// the window and wire up the invoke handler in a single builder call; the
+/// trait exposes that as one blocking `run` rather than forcing an artificial
+/// split.
+pub trait WebViewBackend<Engine>
+where
+    Engine: EngineTrait,
+    Engine::Action: ActionTrait + DeserializeOwned + Sized + Clone,
+    Engine::Response: ResponseTrait + Default + Serialize + std::fmt::Display,
+{
+    /// Build the window from `params` and `initial_html`, register the
+    /// invoke handler that drives `user_data.engine`, and run the event loop
+    /// until the window closes.
+    fn run(params: WebParams, initial_html: String, user_data: UserData<Engine>) -> Result<()>;
+}
@@ -0,0 +1,149 @@
+//! The default [WebViewBackend] driver, built on the `web-view` crate (MSHTML
+//! on Windows, WebKitGTK on Linux).
+use crate::backend::WebViewBackend;
+use crate::dialog::DialogResult;
+use crate::window_command::WindowCommand;
+use crate::{web_error, web_trace, Emitter, UserData, WebParams};
+use anyhow::Result;
+use narthex_engine_trait::{ActionTrait, EngineTrait, Event, ResponseKind, ResponseTrait};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::de::from_str;
+use anyhow::anyhow;
+use web_view::{escape, Content, WebView};
+
+/// apply the window commands a response carries to `webview`, before the
+/// response is handed to the frontend
+fn apply_window_commands<T>(webview: &mut WebView<T>, commands: &[WindowCommand]) {
+    for command in commands {
+        let result = match command {
+            WindowCommand::SetTitle(title) => webview.set_title(title),
+            WindowCommand::Resize { width, height } => webview.set_size(*width, *height),
+            WindowCommand::SetColor(r, g, b, a) => webview.set_color(*r, *g, *b, *a),
+            WindowCommand::SetFullscreen(fullscreen) => webview.set_fullscreen(*fullscreen),
+        };
+        if let Err(e) = result {
+            web_error!("window command {:?} failed: {:?}", command, e);
+        }
+    }
+}
+
+/// Show the native dialog a `ResponseKind` asked for, if it is a dialog
+/// kind, returning the outcome to feed back to the engine.
+fn show_dialog<T>(webview: &mut WebView<T>, kind: &ResponseKind) -> Option<Result<DialogResult>> {
+    let dialog = webview.dialog();
+    match kind {
+        ResponseKind::OpenFile { filters: _ } => Some(
+            dialog
+                .open_file("Open", "")
+                .map(DialogResult::OpenFile)
+                .map_err(|e| anyhow::anyhow!("open file dialog failed: {:?}", e)),
+        ),
+        ResponseKind::SaveFile { default_path } => Some(
+            dialog
+                .save_file("Save", default_path.as_deref().unwrap_or(""))
+                .map(DialogResult::SaveFile)
+                .map_err(|e| anyhow::anyhow!("save file dialog failed: {:?}", e)),
+        ),
+        ResponseKind::MessageBox { title, body, level } => Some(
+            dialog
+                .message_box(title, body, *level)
+                .map(|_| DialogResult::MessageBoxDismissed)
+                .map_err(|e| anyhow::anyhow!("message box failed: {:?}", e)),
+        ),
+        ResponseKind::Confirm { title, body } => Some(
+            dialog
+                .confirm(title, body)
+                .map(DialogResult::Confirm)
+                .map_err(|e| anyhow::anyhow!("confirm dialog failed: {:?}", e)),
+        ),
+        _ => None,
+    }
+}
+
+/// [WebViewBackend] implementation built on the `web-view` crate.
+pub struct WebViewCrateBackend;
+
+impl<Engine> WebViewBackend<Engine> for WebViewCrateBackend
+where
+    Engine: EngineTrait,
+    Engine::Action: ActionTrait + DeserializeOwned + Sized + Clone,
+    Engine::Response: ResponseTrait + Default + Serialize + std::fmt::Display,
+{
+    fn run(params: WebParams, initial_html: String, user_data: UserData<Engine>) -> Result<()> {
+        let webview: web_view::WebView<UserData<Engine>> = web_view::builder()
+            .title(&params.title)
+            .content(Content::Html(initial_html))
+            .size(params.width, params.height)
+            .resizable(true)
+            .debug(params.debug)
+            .user_data(user_data)
+            .invoke_handler(|webview, arg: &str| {
+                if let Some(localizer) = webview.user_data().localizer.clone() {
+                    if let Some(resolve_js) = crate::l10n::try_handle(arg, &localizer) {
+                        webview.eval(&resolve_js)?;
+                        return Ok(());
+                    }
+                }
+                let action: Engine::Action = {
+                    if params.verbose {
+                        web_trace!("action: {}", &arg);
+                    }
+                    from_str(&arg.to_owned()).unwrap_or_else(|e| {
+                        web_error!("cannot deserialise: {:?}", &e);
+                        panic!("cannot deserialise");
+                    })
+                };
+                let response: Engine::Response = webview
+                    .user_data_mut()
+                    .engine
+                    .execute(action)
+                    .unwrap_or_else(|e| {
+                        web_error!("bad execution: {:?}", &e);
+                        Engine::Response::new_with_error(&format!("bad execution: {:?}", &e))
+                    });
+
+                let response = if let Some(outcome) = show_dialog(webview, response.kind()) {
+                    let outcome = outcome.unwrap_or_else(|e| {
+                        web_error!("dialog failed: {:?}", &e);
+                        DialogResult::MessageBoxDismissed
+                    });
+                    webview
+                        .user_data_mut()
+                        .engine
+                        .handle_event(&Event::Dialog(outcome))
+                } else {
+                    response
+                };
+
+                apply_window_commands(webview, &response.window_commands());
+
+                if response.shutdown_required() {
+                    if let ResponseKind::Error(msg) = response.kind() {
+                        web_error!("system error: {}", msg);
+                    }
+                    webview.exit();
+                } else {
+                    let rs: String = serde_json::ser::to_string(&response).unwrap_or_else(|e| {
+                        web_error!("cannot serialise: {:?}", &e);
+                        panic!("cannot serialise");
+                    });
+                    let rsjs: String = escape(&rs).to_string();
+                    webview.eval(&format!("respond({});", &rsjs))?;
+                }
+                Ok(())
+            })
+            .build()?;
+        let handle = webview.handle();
+        let emitter = Emitter::new(move |script: &str| {
+            let script = script.to_string();
+            handle
+                .dispatch(move |wv| wv.eval(&script))
+                .map_err(|e| anyhow!("cannot dispatch to webview: {:?}", e))
+        });
+        webview.user_data_mut().engine.start(emitter);
+        let mut rres = webview.run()?;
+        let _response = rres.engine.handle_event(&Event::Stop); // ignore the response
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! An alternative [WebViewBackend] driver built on the `wry` crate, enabled
+//! via the `backend-wry` cargo feature. Uses WebView2 on Windows and
+//! WebKitGTK on Linux (the same bundle as the external `wry` crate), instead
+//! of `web-view`'s MSHTML/old-WebKitGTK backends.
+use crate::backend::WebViewBackend;
+use crate::dialog::DialogResult;
+use crate::window_command::WindowCommand;
+use crate::{web_error, web_trace, Emitter, UserData, WebParams};
+use anyhow::{anyhow, Result};
+use narthex_engine_trait::{ActionTrait, EngineTrait, Event, ResponseKind, ResponseTrait};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::de::from_str;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wry::application::event::{Event as WryEvent, StartCause, WindowEvent};
+use wry::application::event_loop::{ControlFlow, EventLoop};
+use wry::application::window::{Fullscreen, WindowBuilder};
+use wry::webview::{RpcRequest, RpcResponse, WebView, WebViewBuilder};
+
+/// events the `wry` driver sends itself through an `EventLoopProxy`, to ask
+/// the event loop (which alone owns the window and the webview) to do
+/// something from code that does not otherwise have access to either
+enum DriverEvent {
+    /// the engine asked to shut down; tear down the same way a
+    /// `CloseRequested` window event does
+    Shutdown,
+    /// [Emitter::emit] was called; evaluate the given script against the
+    /// webview
+    Emit(String),
+}
+
+/// Show the native dialog a `ResponseKind` asked for, if it is a dialog
+/// kind, returning the outcome to feed back to the engine. `wry` has no
+/// bundled dialog support (unlike `web-view`'s `tinyfiledialogs`), so this
+/// always fails loudly rather than silently dropping the request.
+fn show_dialog(kind: &ResponseKind) -> Option<Result<DialogResult>> {
+    match kind {
+        ResponseKind::OpenFile { .. }
+        | ResponseKind::SaveFile { .. }
+        | ResponseKind::MessageBox { .. }
+        | ResponseKind::Confirm { .. } => Some(Err(anyhow!(
+            "native dialogs are not supported by the wry backend (feature backend-wry)"
+        ))),
+        _ => None,
+    }
+}
+
+/// apply the window commands a response carries to the webview's window,
+/// before the response is handed to the frontend
+fn apply_window_commands(webview: &WebView, commands: &[WindowCommand]) {
+    let window = webview.window();
+    for command in commands {
+        match command {
+            WindowCommand::SetTitle(title) => window.set_title(title),
+            WindowCommand::Resize { width, height } => {
+                window.set_inner_size(wry::application::dpi::LogicalSize::new(
+                    *width as f64,
+                    *height as f64,
+                ));
+            }
+            WindowCommand::SetFullscreen(fullscreen) => {
+                window.set_fullscreen(if *fullscreen {
+                    Some(Fullscreen::Borderless(None))
+                } else {
+                    None
+                });
+            }
+            WindowCommand::SetColor(..) => {
+                web_error!(
+                    "window command {:?} is not supported by the wry backend (feature backend-wry); ignoring",
+                    command
+                );
+            }
+        }
+    }
+}
+
+/// [WebViewBackend] implementation built on `wry`.
+pub struct WryBackend;
+
+impl<Engine> WebViewBackend<Engine> for WryBackend
+where
+    Engine: EngineTrait,
+    Engine::Action: ActionTrait + DeserializeOwned + Sized + Clone,
+    Engine::Response: ResponseTrait + Default + Serialize + std::fmt::Display,
+{
+    fn run(params: WebParams, initial_html: String, user_data: UserData<Engine>) -> Result<()> {
+        let user_data = Rc::new(RefCell::new(user_data));
+        let rpc_user_data = user_data.clone();
+        let event_loop = EventLoop::<DriverEvent>::with_user_event();
+        let shutdown_proxy = event_loop.create_proxy();
+        let emit_proxy = event_loop.create_proxy();
+        let window = WindowBuilder::new()
+            .with_title(&params.title)
+            .with_inner_size(wry::application::dpi::LogicalSize::new(
+                params.width as f64,
+                params.height as f64,
+            ))
+            .with_resizable(true)
+            .build(&event_loop)?;
+
+        // filled in with the real webview just below, once built; the rpc
+        // handler needs to reach it (for window commands) but is itself
+        // part of building it, so it can only get a handle to the cell
+        let webview_cell: Rc<RefCell<Option<WebView>>> = Rc::new(RefCell::new(None));
+        let rpc_webview_cell = webview_cell.clone();
+
+        let verbose = params.verbose;
+        let webview = WebViewBuilder::new(window)?
+            .with_html(&initial_html)?
+            .with_rpc_handler(move |_window, req: RpcRequest| {
+                // wry hands us `rpc_method` + `params`; this crate's actions are
+                // always serialized as a single JSON value, so we map the RPC
+                // method call straight onto `from_str::<Engine::Action>`, the
+                // same path the `web-view` backend uses.
+                let arg = req
+                    .params
+                    .unwrap_or(serde_json::Value::Null)
+                    .to_string();
+                if verbose {
+                    web_trace!("action ({}): {}", &req.method, &arg);
+                }
+                if let Some(localizer) = rpc_user_data.borrow().localizer.clone() {
+                    if let Some(resolve_js) = crate::l10n::try_handle(&arg, &localizer) {
+                        if let Some(webview) = rpc_webview_cell.borrow().as_ref() {
+                            if let Err(e) = webview.evaluate_script(&resolve_js) {
+                                web_error!("cannot push to webview: {:?}", e);
+                            }
+                        }
+                        return Some(RpcResponse::new_result(req.id, Some(serde_json::Value::Null)));
+                    }
+                }
+                let action: Engine::Action = match from_str(&arg) {
+                    Ok(action) => action,
+                    Err(e) => {
+                        web_error!("cannot deserialise: {:?}", &e);
+                        return None;
+                    }
+                };
+                let response: Engine::Response =
+                    rpc_user_data.borrow_mut().engine.execute(action).unwrap_or_else(|e| {
+                        web_error!("bad execution: {:?}", &e);
+                        Engine::Response::new_with_error(&format!("bad execution: {:?}", &e))
+                    });
+
+                let response = if let Some(outcome) = show_dialog(response.kind()) {
+                    let outcome = outcome.unwrap_or_else(|e| {
+                        web_error!("dialog failed: {:?}", &e);
+                        DialogResult::MessageBoxDismissed
+                    });
+                    rpc_user_data
+                        .borrow_mut()
+                        .engine
+                        .handle_event(&Event::Dialog(outcome))
+                } else {
+                    response
+                };
+
+                if let Some(webview) = rpc_webview_cell.borrow().as_ref() {
+                    apply_window_commands(webview, &response.window_commands());
+                }
+
+                if response.shutdown_required() {
+                    if let ResponseKind::Error(msg) = response.kind() {
+                        web_error!("system error: {}", msg);
+                    }
+                    let _response = rpc_user_data.borrow_mut().engine.handle_event(&Event::Stop); // ignore the response
+                    if shutdown_proxy.send_event(DriverEvent::Shutdown).is_err() {
+                        web_error!("event loop already gone, cannot drive shutdown");
+                    }
+                    return Some(RpcResponse::new_error(req.id, Some(serde_json::Value::Null)));
+                }
+                // deliver via `respond(...)`, exactly like the web-view
+                // backend and a pushed `Emitter::emit`, so a frontend only
+                // ever has one channel to listen on regardless of backend
+                let script = crate::push::respond_script(&response).unwrap_or_else(|e| {
+                    web_error!("cannot serialise: {:?}", &e);
+                    panic!("cannot serialise");
+                });
+                if let Some(webview) = rpc_webview_cell.borrow().as_ref() {
+                    if let Err(e) = webview.evaluate_script(&script) {
+                        web_error!("cannot push to webview: {:?}", e);
+                    }
+                }
+                Some(RpcResponse::new_result(req.id, Some(serde_json::Value::Null)))
+            })
+            .build()?;
+        *webview_cell.borrow_mut() = Some(webview);
+
+        let emitter = Emitter::new(move |script: &str| {
+            emit_proxy
+                .send_event(DriverEvent::Emit(script.to_string()))
+                .map_err(|_| anyhow!("event loop already gone, cannot push to webview"))
+        });
+        user_data.borrow_mut().engine.start(emitter);
+
+        event_loop.run(move |event, _, control_flow| {
+            *control_flow = ControlFlow::Wait;
+            match event {
+                WryEvent::NewEvents(StartCause::Init) => web_trace!("wry event loop started"),
+                WryEvent::WindowEvent {
+                    event: WindowEvent::CloseRequested,
+                    ..
+                } => {
+                    let _response = user_data.borrow_mut().engine.handle_event(&Event::Stop); // ignore the response
+                    *control_flow = ControlFlow::Exit;
+                }
+                WryEvent::UserEvent(DriverEvent::Shutdown) => {
+                    // `Event::Stop` was already fired from the rpc handler,
+                    // before it asked us (via `shutdown_proxy`) to unwind the loop
+                    *control_flow = ControlFlow::Exit;
+                }
+                WryEvent::UserEvent(DriverEvent::Emit(script)) => {
+                    if let Some(webview) = webview_cell.borrow().as_ref() {
+                        if let Err(e) = webview.evaluate_script(&script) {
+                            web_error!("cannot push to webview: {:?}", e);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialog::DialogLevel;
+
+    #[test]
+    fn show_dialog_flags_every_dialog_kind_as_unsupported() {
+        let kinds = [
+            ResponseKind::OpenFile { filters: vec![] },
+            ResponseKind::SaveFile { default_path: None },
+            ResponseKind::MessageBox {
+                title: "title".to_string(),
+                body: "body".to_string(),
+                level: DialogLevel::Info,
+            },
+            ResponseKind::Confirm {
+                title: "title".to_string(),
+                body: "body".to_string(),
+            },
+        ];
+        for kind in &kinds {
+            let outcome = show_dialog(kind).expect("dialog kind should be handled");
+            let err = outcome.unwrap_err();
+            assert!(err.to_string().contains("not supported"));
+        }
+    }
+
+    #[test]
+    fn show_dialog_ignores_non_dialog_kinds() {
+        assert!(show_dialog(&ResponseKind::Error("boom".to_string())).is_none());
+    }
+}
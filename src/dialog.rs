@@ -0,0 +1,33 @@
+//! Native dialogs (file pickers, message boxes), requested by the engine via
+//! `ResponseKind` and shown with the `tinyfiledialogs` support the
+//! `web-view` crate already bundles. The chosen path / boolean / dismissal
+//! is fed back to the engine via [narthex_engine_trait::Event::Dialog] so
+//! the flow stays a clean action -> response loop instead of engines having
+//! to write JS to drive file I/O or confirmations themselves.
+use serde::{Deserialize, Serialize};
+
+/// severity of a `MessageBox` dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DialogLevel {
+    /// informational message box
+    Info,
+    /// warning message box
+    Warning,
+    /// error message box
+    Error,
+}
+
+/// outcome of a dialog shown in response to a dialog `ResponseKind`, handed
+/// back to the engine via `Event::Dialog`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DialogResult {
+    /// path chosen by an `OpenFile` dialog, or `None` if the user dismissed it
+    OpenFile(Option<String>),
+    /// path chosen by a `SaveFile` dialog, or `None` if the user dismissed it
+    SaveFile(Option<String>),
+    /// a `MessageBox` was dismissed; also sent in place of any dialog's
+    /// outcome when that dialog could not be shown
+    MessageBoxDismissed,
+    /// the user's choice in a `Confirm` dialog
+    Confirm(bool),
+}
@@ -0,0 +1,332 @@
+//! Fluent (FTL) localization.
+//!
+//! Lets apps ship per-locale `.ftl` bundles instead of hardcoding strings.
+//! [Localizer] loads the `.ftl` resources for one locale into a
+//! `fluent_bundle::FluentBundle` and resolves message IDs with named
+//! arguments, including Fluent's selector/plural handling (a message body
+//! like `{ $count -> [one] 1 item *[other] {$count} items }` picks a
+//! variant by argument value). It is handed to the engine so
+//! `execute`/`initial_html` can localize server-side; [Localizer::inject]
+//! adds a JS `t(id, args)` shim plus the resolved catalog to the initial
+//! page so the frontend can localize too. Argument-bearing lookups can only
+//! be resolved once the arguments are known at call time, so `t` resolves
+//! those asynchronously via [try_handle]; see its doc comment.
+//!
+//! Missing-key lookups fall back to the message ID itself and log a
+//! `web_error!`; they never panic.
+use crate::web_error;
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use fluent_syntax::ast::{Entry, Expression, InlineExpression, Pattern, PatternElement};
+use serde::Deserialize;
+use std::collections::HashMap;
+use unic_langid::LanguageIdentifier;
+
+/// Resolves Fluent message IDs against the bundle for one locale.
+pub struct Localizer {
+    locale: LanguageIdentifier,
+    bundle: FluentBundle<FluentResource>,
+    /// ids of messages that take no arguments, for [Localizer::static_catalog]
+    static_message_ids: Vec<String>,
+}
+
+impl Localizer {
+    /// Parse `locale` (or, if it is `None`, the `LANG` environment
+    /// variable), falling back to `en-US` if that is not a valid BCP-47
+    /// tag; an invalid `locale` goes straight to `en-US`. Then load
+    /// `ftl_sources` (the contents of the locale's `.ftl` files) into a
+    /// bundle for it.
+    pub fn new(locale: Option<&str>, ftl_sources: Vec<String>) -> Self {
+        let locale: LanguageIdentifier = locale
+            .map(str::to_string)
+            .or_else(|| std::env::var("LANG").ok())
+            .and_then(|tag| tag.split('.').next().map(str::to_string))
+            // POSIX locales are `ll_CC` (underscore); BCP-47 wants `ll-CC`
+            .map(|tag| tag.replace('_', "-"))
+            .and_then(|tag| tag.parse().ok())
+            .unwrap_or_else(|| "en-US".parse().expect("en-US is a valid language tag"));
+        let mut bundle = FluentBundle::new(vec![locale.clone()]);
+        // resolved text is plain, without Unicode isolation marks around placeables
+        bundle.set_use_isolating(false);
+        let mut static_message_ids = vec![];
+        for source in ftl_sources {
+            match FluentResource::try_new(source) {
+                Ok(resource) => {
+                    for entry in resource.entries() {
+                        if let Entry::Message(message) = entry {
+                            if matches!(&message.value, Some(pattern) if !pattern_needs_args(pattern))
+                            {
+                                static_message_ids.push(message.id.name.to_string());
+                            }
+                        }
+                    }
+                    if let Err(errors) = bundle.add_resource(resource) {
+                        for e in errors {
+                            web_error!("fluent resource error: {:?}", e);
+                        }
+                    }
+                }
+                Err((_, errors)) => {
+                    for e in errors {
+                        web_error!("fluent parse error: {:?}", e);
+                    }
+                }
+            }
+        }
+        Self {
+            locale,
+            bundle,
+            static_message_ids,
+        }
+    }
+
+    /// the locale this localizer resolves messages for
+    pub fn locale(&self) -> &LanguageIdentifier {
+        &self.locale
+    }
+
+    /// Resolve `id` with `args`, falling back to `id` itself (logging a
+    /// `web_error!`) if the message, or its value, is missing.
+    pub fn l10n(&self, id: &str, args: &HashMap<String, FluentValue>) -> String {
+        let message = match self.bundle.get_message(id) {
+            Some(message) => message,
+            None => {
+                web_error!("missing localization key: {}", id);
+                return id.to_string();
+            }
+        };
+        let pattern = match message.value() {
+            Some(pattern) => pattern,
+            None => {
+                web_error!("localization key {} has no value", id);
+                return id.to_string();
+            }
+        };
+        let fluent_args: FluentArgs = args
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let mut errors = vec![];
+        let resolved = self
+            .bundle
+            .format_pattern(pattern, Some(&fluent_args), &mut errors);
+        for e in errors {
+            web_error!("fluent format error resolving {}: {:?}", id, e);
+        }
+        resolved.into_owned()
+    }
+
+    /// Resolve every argument-free message in the bundle, for the catalog
+    /// handed to the frontend; messages needing arguments are omitted from
+    /// the catalog and must go through the JS `t(id, args)` shim, which
+    /// round-trips to the backend's invoke handler.
+    fn static_catalog(&self) -> HashMap<String, String> {
+        self.static_message_ids
+            .iter()
+            .map(|id| (id.clone(), self.l10n(id, &HashMap::new())))
+            .collect()
+    }
+
+    /// Inject a JS `t(id, args)` shim and the resolved static catalog into
+    /// `html`, just before `</head>` (or at the end if there is none), so
+    /// the frontend can localize without a round-trip for argument-free
+    /// messages. `t` always returns a `Promise`: argument-free lookups
+    /// resolve immediately from the injected catalog, argument-bearing
+    /// ones resolve once a backend's invoke handler has run the request
+    /// through [try_handle] and called `__l10n_resolve(nonce, text)`.
+    pub fn inject(&self, html: &str) -> String {
+        let catalog = serde_json::to_string(&self.static_catalog())
+            .unwrap_or_else(|_| "{}".to_string());
+        let script = format!(
+            "<script>\n\
+             window.__l10n_catalog = {catalog};\n\
+             window.__l10n_pending = {{}};\n\
+             window.__l10n_nonce = 0;\n\
+             function t(id, args) {{\n\
+             \x20\x20if ((!args || Object.keys(args).length === 0) && window.__l10n_catalog[id] !== undefined) {{\n\
+             \x20\x20\x20\x20return Promise.resolve(window.__l10n_catalog[id]);\n\
+             \x20\x20}}\n\
+             \x20\x20var nonce = window.__l10n_nonce++;\n\
+             \x20\x20return new Promise(function(resolve) {{\n\
+             \x20\x20\x20\x20window.__l10n_pending[nonce] = resolve;\n\
+             \x20\x20\x20\x20invoke(JSON.stringify({{ L10n: {{ nonce: nonce, id: id, args: args || {{}} }} }}));\n\
+             \x20\x20}});\n\
+             }}\n\
+             function __l10n_resolve(nonce, text) {{\n\
+             \x20\x20var resolve = window.__l10n_pending[nonce];\n\
+             \x20\x20if (resolve) {{\n\
+             \x20\x20\x20\x20delete window.__l10n_pending[nonce];\n\
+             \x20\x20\x20\x20resolve(text);\n\
+             \x20\x20}}\n\
+             }}\n\
+             </script>",
+            catalog = catalog
+        );
+        match html.find("</head>") {
+            Some(at) => {
+                let mut out = String::with_capacity(html.len() + script.len());
+                out.push_str(&html[..at]);
+                out.push_str(&script);
+                out.push_str(&html[at..]);
+                out
+            }
+            None => format!("{html}{script}"),
+        }
+    }
+}
+
+/// body of the `{"L10n": {...}}` request the `t(id, args)` JS shim sends
+/// via `invoke`, ahead of any `Engine::Action` the frontend might also send
+#[derive(Deserialize)]
+struct L10nRequest {
+    nonce: u64,
+    id: String,
+    #[serde(default)]
+    args: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct L10nEnvelope {
+    #[serde(rename = "L10n")]
+    l10n: L10nRequest,
+}
+
+/// If `arg` (the raw string a backend's invoke handler received) is a
+/// `{"L10n": {...}}` request from the `t(id, args)` JS shim, resolve it
+/// against `localizer` and return the JS to `eval` so it settles the
+/// pending promise via `__l10n_resolve`. Otherwise returns `None`, meaning
+/// the caller should deserialize `arg` as an `Engine::Action` as usual.
+pub fn try_handle(arg: &str, localizer: &Localizer) -> Option<String> {
+    let envelope: L10nEnvelope = serde_json::from_str(arg).ok()?;
+    let args = envelope
+        .l10n
+        .args
+        .into_iter()
+        .map(|(k, v)| (k, json_to_fluent(v)))
+        .collect();
+    let text = localizer.l10n(&envelope.l10n.id, &args);
+    let text_js = serde_json::to_string(&text).unwrap_or_else(|_| "\"\"".to_string());
+    Some(format!(
+        "__l10n_resolve({}, {});",
+        envelope.l10n.nonce, text_js
+    ))
+}
+
+fn json_to_fluent(value: serde_json::Value) -> FluentValue<'static> {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_f64()
+            .map(FluentValue::from)
+            .unwrap_or_else(|| FluentValue::String(n.to_string().into())),
+        serde_json::Value::String(s) => FluentValue::String(s.into()),
+        other => FluentValue::String(other.to_string().into()),
+    }
+}
+
+/// whether resolving `pattern` requires caller-supplied arguments (a
+/// variable reference anywhere in its text, placeables, or selectors) --
+/// such messages can't go in [Localizer::static_catalog]
+fn pattern_needs_args<S: AsRef<str>>(pattern: &Pattern<S>) -> bool {
+    pattern.elements.iter().any(|element| match element {
+        PatternElement::TextElement(_) => false,
+        PatternElement::Placeable(expression) => expression_needs_args(expression),
+    })
+}
+
+fn expression_needs_args<S: AsRef<str>>(expression: &Expression<S>) -> bool {
+    match expression {
+        Expression::Inline(inline) => inline_needs_args(inline),
+        Expression::Select { selector, variants } => {
+            inline_needs_args(selector) || variants.iter().any(|v| pattern_needs_args(&v.value))
+        }
+    }
+}
+
+fn inline_needs_args<S: AsRef<str>>(inline: &InlineExpression<S>) -> bool {
+    match inline {
+        InlineExpression::VariableReference { .. } => true,
+        InlineExpression::FunctionReference { arguments, .. } => {
+            arguments.positional.iter().any(expression_needs_args)
+                || arguments
+                    .named
+                    .iter()
+                    .any(|arg| inline_needs_args(&arg.value))
+        }
+        InlineExpression::Placeable { expression } => expression_needs_args(expression),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn localizer(ftl: &str) -> Localizer {
+        Localizer::new(Some("en-US"), vec![ftl.to_string()])
+    }
+
+    #[test]
+    fn resolves_a_plain_message() {
+        let l = localizer("hello = Hello, world!\n");
+        assert_eq!(l.l10n("hello", &HashMap::new()), "Hello, world!");
+    }
+
+    #[test]
+    fn resolves_a_message_with_a_named_argument() {
+        let l = localizer("greet = Hello, { $name }!\n");
+        let mut args = HashMap::new();
+        args.insert("name".to_string(), FluentValue::from("Ada"));
+        assert_eq!(l.l10n("greet", &args), "Hello, Ada!");
+    }
+
+    #[test]
+    fn resolves_a_plural_selector() {
+        let l = localizer(
+            "items = { $count ->\n    [one] 1 item\n   *[other] { $count } items\n}\n",
+        );
+        let mut one = HashMap::new();
+        one.insert("count".to_string(), FluentValue::from(1));
+        assert_eq!(l.l10n("items", &one), "1 item");
+        let mut many = HashMap::new();
+        many.insert("count".to_string(), FluentValue::from(5));
+        assert_eq!(l.l10n("items", &many), "5 items");
+    }
+
+    #[test]
+    fn missing_key_falls_back_to_the_id_without_panicking() {
+        let l = localizer("hello = Hello, world!\n");
+        assert_eq!(l.l10n("does-not-exist", &HashMap::new()), "does-not-exist");
+    }
+
+    #[test]
+    fn static_catalog_omits_messages_that_need_arguments() {
+        let l = localizer("hello = Hello, world!\ngreet = Hello, { $name }!\n");
+        let catalog = l.static_catalog();
+        assert_eq!(
+            catalog.get("hello").map(String::as_str),
+            Some("Hello, world!")
+        );
+        assert!(!catalog.contains_key("greet"));
+    }
+
+    #[test]
+    fn inject_places_the_script_before_head_close() {
+        let l = localizer("hello = Hello, world!\n");
+        let html = l.inject("<html><head></head><body></body></html>");
+        let head_close = html.find("</head>").unwrap();
+        let script_open = html.find("<script>").unwrap();
+        assert!(script_open < head_close);
+    }
+
+    #[test]
+    fn try_handle_resolves_an_l10n_request_and_ignores_other_actions() {
+        let l = localizer("greet = Hello, { $name }!\n");
+        let js = try_handle(
+            r#"{"L10n": {"nonce": 3, "id": "greet", "args": {"name": "Ada"}}}"#,
+            &l,
+        )
+        .expect("should recognise an L10n request");
+        assert!(js.contains("__l10n_resolve(3,"));
+        assert!(js.contains("Hello, Ada!"));
+        assert!(try_handle(r#"{"SomeAction": {}}"#, &l).is_none());
+    }
+}
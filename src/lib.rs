@@ -1,24 +1,42 @@
 /*! This file provides a way of constructing a webview based app. The idea is that the app developer provides an 'engine' that satisfies the [narthex_engine_trait] plus a simple main progrem, and the result is an app. See [narthex_engine_trait] for more information. See `narthex_wumpus` for an example of a main program that uses this crate. */
-use ansi_term::Colour::*;
 use anyhow::Result;
-use log::{trace, error};
-use narthex_engine_trait::{ActionTrait, EngineTrait, Event, ResponseKind, ResponseTrait};
+use narthex_engine_trait::{ActionTrait, EngineTrait, ResponseTrait};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde_json::de::from_str;
-use web_view::{escape, Content, WebView};
+#[macro_export]
 macro_rules! web_trace {
-    () => { trace!() };
+    () => { ::log::trace!() };
     ($($arg:tt)*) => {
-        trace!("{} ({}:{})", Green.on(Black).paint(format!($($arg)*)), std::file!(), std::line!());
+        ::log::trace!("{} ({}:{})", ::ansi_term::Colour::Green.on(::ansi_term::Colour::Black).paint(format!($($arg)*)), std::file!(), std::line!());
     };
 }
+#[macro_export]
 macro_rules! web_error {
-    () => { error!() };
+    () => { ::log::error!() };
     ($($arg:tt)*) => {
-        error!("{} ({}:{})", Red.on(Black).paint(format!($($arg)*)), std::file!(), std::line!());
+        ::log::error!("{} ({}:{})", ::ansi_term::Colour::Red.on(::ansi_term::Colour::Black).paint(format!($($arg)*)), std::file!(), std::line!());
     };
 }
+#[cfg(all(feature = "backend-web-view", feature = "backend-wry"))]
+compile_error!("enable exactly one of the `backend-web-view` / `backend-wry` features, not both");
+#[cfg(not(any(feature = "backend-web-view", feature = "backend-wry")))]
+compile_error!("enable exactly one of the `backend-web-view` / `backend-wry` features");
+mod backend;
+#[cfg(feature = "backend-web-view")]
+mod backend_web_view;
+#[cfg(feature = "backend-wry")]
+mod backend_wry;
+mod asset;
+mod dialog;
+mod l10n;
+mod push;
+mod window_command;
+pub use asset::build_initial_html;
+pub use backend::WebViewBackend;
+pub use dialog::{DialogLevel, DialogResult};
+pub use l10n::Localizer;
+pub use push::Emitter;
+pub use window_command::WindowCommand;
 /// parameters to running the engine
 #[derive(Debug)]
 pub struct WebParams {
@@ -32,6 +50,11 @@ pub struct WebParams {
     pub width: i32,
     /// Whether to show extra debug trace
     pub verbose: bool,
+    /// BCP-47 locale to localize into (e.g. `"fr-FR"`); falls back to the
+    /// `LANG` environment variable, then `en-US`, if unset
+    pub locale: Option<String>,
+    /// contents of the `.ftl` resources for `locale`
+    pub ftl_sources: Vec<String>,
 }
 impl Default for WebParams {
     fn default() -> Self {
@@ -41,12 +64,19 @@ impl Default for WebParams {
             width: 640,
             height: 960,
             verbose: false,
+            locale: None,
+            ftl_sources: vec![],
         }
     }
 }
 /** used by [web_view::WebView] */
 pub struct UserData<Engine: EngineTrait> {
     engine: Engine,
+    /// set by [UserData::run_engine_with_webview] before the backend builds
+    /// its window, so a backend's invoke handler can resolve `t(id, args)`
+    /// requests itself, via [l10n::try_handle], without going through
+    /// `Engine::execute`
+    localizer: Option<std::rc::Rc<Localizer>>,
 }
 impl<Engine> UserData<Engine>
 where
@@ -56,71 +86,26 @@ where
 {
     /// create
     pub fn new(engine: Engine) -> UserData<Engine> {
-        UserData { engine }
+        UserData {
+            engine,
+            localizer: None,
+        }
     }
-    /// build the web view and run the engine
+    /// build the web view and run the engine, via whichever [WebViewBackend]
+    /// is selected by cargo features (see [crate::backend])
     pub fn run_engine_with_webview(mut self, params: WebParams) -> Result<()> {
         web_trace!("running with engine, web view params are {:?}", &params);
-        let initial_html = self.engine.initial_html()?;
-        let webview: WebView<UserData<Engine>> = web_view::builder()
-            .title(&params.title)
-            .content(Content::Html(initial_html))
-            .size(params.width, params.height)
-            .resizable(true)
-            .debug(params.debug)
-            .user_data(self)
-            .invoke_handler(|webview, arg: &str| {
-                let action: Engine::Action = {
-                    if params.verbose {
-                        web_trace!("action: {}", &arg);
-                    }
-                    let action = from_str(&arg.to_owned()).unwrap_or_else(|e| {
-                        web_error!("cannot deserialise: {:?}", &e);
-                        panic!("cannot deserialise");
-                    });
-                    action
-                };
-                let response: Engine::Response = webview
-                    .user_data_mut()
-                    .engine
-                    .execute(action)
-                    .unwrap_or_else(|e| {
-                        web_error!("bad execution: {:?}", &e);
-                        Engine::Response::new_with_error(&format!("bad execution: {:?}", &e))
-                    });
-
-                if response.shutdown_required() {
-                    // web_trace!("shutting down because response received: {}", &response);
-                    if let ResponseKind::Error(msg) = response.kind() {
-                        web_error!("system error: {}", msg);
-                    }
-                    webview.exit();
-                } else {
-                    let rs: String = serde_json::ser::to_string(&response).unwrap_or_else(|e| {
-                        web_error!("cannot serialise: {:?}", &e);
-                        panic!("cannot serialise");
-                    });
-                    //                    web_trace!(
-                    //                        "response: {}",
-                    //                        if rs.len() < 105 { &rs } else { &rs[..100] }
-                    //                    );
-                    let rsjs: String = escape(&rs).to_string();
-                    //                    web_trace!(
-                    //                        "resp to js: {}",
-                    //                        if rsjs.len() < 105 {
-                    //                            &rsjs
-                    //                        } else {
-                    //                            &rsjs[..100]
-                    //                        }
-                    //                    );
-                    webview.eval(&format!("respond({});", &rsjs))?;
-                }
-                Ok(())
-            })
-            .build()?;
-        let mut rres = webview.run()?;
-        let _response = rres.engine.handle_event(&Event::Stop); // ignore the response
-        Ok(())
+        let localizer = std::rc::Rc::new(Localizer::new(
+            params.locale.as_deref(),
+            params.ftl_sources.clone(),
+        ));
+        self.engine.set_localizer(localizer.clone());
+        self.localizer = Some(localizer.clone());
+        let initial_html = localizer.inject(&self.engine.initial_html()?);
+        #[cfg(feature = "backend-wry")]
+        return crate::backend_wry::WryBackend::run(params, initial_html, self);
+        #[cfg(feature = "backend-web-view")]
+        return crate::backend_web_view::WebViewCrateBackend::run(params, initial_html, self);
     }
 }
 /* This Source Code Form is subject to the terms of the Mozilla Public
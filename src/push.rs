@@ -0,0 +1,82 @@
+//! Engine-initiated event push.
+//!
+//! `execute` only ever answers synchronously, in response to a frontend
+//! action; there was previously no way for the engine to speak first (a
+//! timer firing, a background job finishing, a streamed partial result).
+//! [Emitter] closes that gap: a backend hands one to the engine before
+//! running its event loop (see `EngineTrait::start`), and the engine can
+//! stash it (e.g. move it into a background thread) and call [Emitter::emit]
+//! whenever it has something to push.
+use anyhow::Result;
+use narthex_engine_trait::EngineTrait;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+/// A `Send`-able handle an engine can use to push a response to the
+/// frontend at any time, from any thread, by evaling `respond(...)` in the
+/// webview. Each [WebViewBackend](crate::WebViewBackend) builds one around
+/// whatever mechanism it has for reaching back into its own window from
+/// outside `execute()` (`web_view::Handle::dispatch`, a `wry`
+/// `EventLoopProxy`, ...), so [push] itself stays independent of any one
+/// backend crate.
+pub struct Emitter<Engine: EngineTrait> {
+    dispatch: Box<dyn Fn(&str) -> Result<()> + Send>,
+    _engine: PhantomData<Engine>,
+}
+
+impl<Engine> Emitter<Engine>
+where
+    Engine: EngineTrait,
+    Engine::Response: Serialize,
+{
+    /// wrap a backend-specific `dispatch` closure that runs a script
+    /// against the webview, from whatever thread the backend requires
+    pub fn new(dispatch: impl Fn(&str) -> Result<()> + Send + 'static) -> Self {
+        Self {
+            dispatch: Box::new(dispatch),
+            _engine: PhantomData,
+        }
+    }
+    /// Push `resp` to the frontend. Returns `Err` once the window has
+    /// closed, at which point the caller must stop producing further
+    /// events (the dispatch queue no longer has anywhere to deliver to).
+    pub fn emit(&self, resp: &Engine::Response) -> Result<()> {
+        (self.dispatch)(&respond_script(resp)?)
+    }
+}
+
+/// build the `respond(...)` script that delivers `resp` to the frontend,
+/// shared by [Emitter::emit] and any backend that delivers normal
+/// `execute` responses the same way a pushed one would (so a frontend
+/// written against the single `respond(...)` delivery channel sees both
+/// alike, regardless of backend).
+pub(crate) fn respond_script<Response: Serialize>(resp: &Response) -> Result<String> {
+    let rs = serde_json::ser::to_string(resp)?;
+    let rsjs = serde_json::to_string(&rs).unwrap_or_else(|_| "\"\"".to_string());
+    Ok(format!("respond({});", &rsjs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Sample {
+        greeting: String,
+    }
+
+    #[test]
+    fn respond_script_wraps_the_escaped_response_in_a_respond_call() {
+        let resp = Sample {
+            greeting: "hi \"there\"".to_string(),
+        };
+        let script = respond_script(&resp).unwrap();
+        assert!(script.starts_with("respond(") && script.ends_with(");"));
+        // the body must be a JS string literal that, once parsed, decodes
+        // back to the plain (unescaped) serialized response - this is the
+        // double-serialization `emit`/wry's response delivery depend on
+        let literal = &script["respond(".len()..script.len() - ");".len()];
+        let decoded: String = serde_json::from_str(literal).unwrap();
+        assert_eq!(decoded, serde_json::ser::to_string(&resp).unwrap());
+    }
+}
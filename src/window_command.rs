@@ -0,0 +1,27 @@
+//! Runtime window-chrome control.
+//!
+//! [WebParams] only ever sets the title and size once, at build time, so an
+//! engine has no way to reflect state (unsaved changes, theme) in the
+//! window itself after startup. A [WindowCommand] lets a `Response` ask for
+//! one; `ResponseTrait::window_commands` carries zero or more of them and a
+//! backend applies them before evaling `respond(...)`.
+use serde::{Deserialize, Serialize};
+
+/// a runtime change to the window chrome, applied after `execute` and
+/// before the response reaches the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WindowCommand {
+    /// change the window title
+    SetTitle(String),
+    /// resize the window, in the same units as [crate::WebParams::width]/[crate::WebParams::height]
+    Resize {
+        /// new width
+        width: i32,
+        /// new height
+        height: i32,
+    },
+    /// change the window background colour (RGBA, 0-255 per channel)
+    SetColor(u8, u8, u8, u8),
+    /// enter or leave fullscreen
+    SetFullscreen(bool),
+}